@@ -1,6 +1,12 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 use concordium_std::*;
 
+/// Depth of the incremental commitment tree; supports up to 2^20 leaves.
+pub const TREE_DEPTH: usize = 20;
+/// How many recent roots stay valid for `use_nullifier`, so a prover building
+/// against a root that was since superseded by a new insert isn't stranded.
+pub const ROOT_HISTORY_SIZE: usize = 64;
+
 /// 32-byte identity commitment.
 #[derive(Serial, Deserial, SchemaType, Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Commitment(pub [u8; 32]);
@@ -16,6 +22,114 @@ pub struct NullifierKey {
     pub nullifier: Nullifier,
 }
 
+/// Lifecycle status of a credential, computed against the current slot time.
+#[derive(Serial, Deserial, SchemaType, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CredentialStatus {
+    NotActivated,
+    Active,
+    Expired,
+    Revoked,
+}
+
+/// A registered credential: the commitment plus its validity window and
+/// revocation state, replacing the old bare `Commitment` membership test.
+#[derive(Serial, Deserial, SchemaType, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CredentialRecord {
+    pub commitment: Commitment,
+    pub valid_from: Timestamp,
+    pub valid_until: Option<Timestamp>,
+    pub revoked_at: Option<Timestamp>,
+    /// The holder's own key, if registered with one, letting the subject
+    /// revoke their own credential via `revoke_by_holder` without the IdP.
+    pub holder_key: Option<PublicKeyEd25519>,
+}
+
+impl CredentialRecord {
+    fn status(&self, now: Timestamp) -> CredentialStatus {
+        if self.revoked_at.is_some() {
+            return CredentialStatus::Revoked;
+        }
+        if now < self.valid_from {
+            return CredentialStatus::NotActivated;
+        }
+        if let Some(valid_until) = self.valid_until {
+            if now >= valid_until {
+                return CredentialStatus::Expired;
+            }
+        }
+        CredentialStatus::Active
+    }
+}
+
+/// A URL plus an optional hash of its content, used for off-chain metadata
+/// references (CIS-2/CIS-4 style `MetadataUrl`).
+#[derive(Serial, Deserial, SchemaType, Clone, PartialEq, Eq, Debug)]
+pub struct MetadataUrl {
+    pub url: String,
+    pub hash: Option<[u8; 32]>,
+}
+
+/// CIS-0 standard identifier, e.g. `"CIS-4"`.
+#[derive(Serial, Deserial, SchemaType, Clone, Debug)]
+pub struct StandardIdentifierOwned(pub String);
+
+#[derive(Serial, Deserial, SchemaType)]
+pub struct SupportsQueryParams {
+    pub queries: Vec<StandardIdentifierOwned>,
+}
+
+/// Whether (and how) a given standard is supported, per CIS-0.
+#[derive(Serial, Deserial, SchemaType, Clone, PartialEq, Eq, Debug)]
+pub enum SupportResult {
+    NoSupport,
+    Support,
+    SupportBy(Vec<ContractAddress>),
+}
+
+#[derive(Serial, Deserial, SchemaType)]
+pub struct SupportsQueryResponse {
+    pub results: Vec<SupportResult>,
+}
+
+/// CIS-4-style registry metadata: where to find the issuer's own metadata
+/// and the JSON schema credentials in this registry conform to.
+#[derive(Serial, Deserial, SchemaType, Clone, Debug)]
+pub struct RegistryMetadata {
+    pub issuer_metadata: MetadataUrl,
+    pub credential_schema_url: MetadataUrl,
+}
+
+/// A subject keyed by their on-chain account, used as the CIS-4-style
+/// credential identifier for `credentialStatus` lookups.
+#[derive(Serial, Deserial, SchemaType, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CredentialId(pub AccountAddress);
+
+/// Why a holder revoked their own credential, signed over alongside the
+/// subject and nonce so a `revoke_by_holder` signature can't be replayed
+/// against a different message shape.
+#[derive(Serial, Deserial, SchemaType, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RevokeReason {
+    HolderRequested,
+    KeyCompromised,
+}
+
+/// CIS-4-compatible tagged events, logged alongside the existing `Event`
+/// enum so standard indexers and the Concordium wallet can track
+/// credential lifecycle without parsing our bespoke event shapes.
+#[derive(Serial, SchemaType, Debug)]
+pub enum Cis4Event {
+    RegisterCredentialEvent {
+        subject: AccountAddress,
+        commitment: Commitment,
+        valid_from: Timestamp,
+        valid_until: Option<Timestamp>,
+    },
+    RevokeCredentialEvent {
+        subject: AccountAddress,
+        timestamp: Timestamp,
+    },
+}
+
 #[derive(Reject, Serial, SchemaType, Debug, PartialEq, Eq)]
 pub enum Error {
     Unauthorized,
@@ -24,6 +138,11 @@ pub enum Error {
     NotRegistered,
     NullifierUsed,
     Parse,
+    NotIdpKey,
+    InvalidSignature,
+    InvalidNonce,
+    TreeFull,
+    UnknownRoot,
 }
 pub type ContractResult<T> = Result<T, Error>;
 
@@ -33,18 +152,32 @@ pub enum Event {
     IdpAdded { 
         idp: AccountAddress 
     },
-    IdpRemoved { 
-        idp: AccountAddress 
+    IdpRemoved {
+        idp: AccountAddress
+    },
+    IdpKeyAdded {
+        key: PublicKeyEd25519,
+    },
+    IdpKeyRemoved {
+        key: PublicKeyEd25519,
     },
-    Registered { 
-        idp: AccountAddress, 
-        subject: AccountAddress, 
+    Registered {
+        idp: Option<AccountAddress>,
+        signer_key: Option<PublicKeyEd25519>,
+        subject: AccountAddress,
         commitment: Commitment,
+        leaf_index: u64,
+        timestamp: Timestamp,
+    },
+    Revoked {
+        idp: Option<AccountAddress>,
+        subject: AccountAddress,
         timestamp: Timestamp,
     },
-    Revoked { 
-        idp: AccountAddress, 
+    Renewed {
+        idp: AccountAddress,
         subject: AccountAddress,
+        valid_until: Option<Timestamp>,
         timestamp: Timestamp,
     },
     NullifierUsed { 
@@ -66,23 +199,127 @@ pub enum Event {
 pub struct State {
     admin: AccountAddress,
     idps: StateSet<AccountAddress, StateApi>,
-    verified: StateMap<AccountAddress, Commitment, StateApi>,
+    idp_keys: StateSet<PublicKeyEd25519, StateApi>,
+    verified: StateMap<AccountAddress, CredentialRecord, StateApi>,
     used_nullifiers: StateSet<NullifierKey, StateApi>,
-    revoked_at: StateMap<AccountAddress, Timestamp, StateApi>, // Track revocation timestamps
+    nonces: StateMap<AccountAddress, u64, StateApi>, // Replay protection for register_signed
+    revocation_nonces: StateMap<AccountAddress, u64, StateApi>, // Replay protection for revoke_by_holder
+    registry_metadata: RegistryMetadata,
+    // Incremental commitment tree (Semaphore/Tornado-style), so membership can
+    // be proven against a published root without revealing which leaf a
+    // subject's commitment landed at.
+    next_leaf_index: u64,
+    filled_subtrees: Vec<[u8; 32]>,
+    zero_hashes: Vec<[u8; 32]>,
+    current_root: [u8; 32],
+    known_roots: StateSet<[u8; 32], StateApi>,
+    root_history: Vec<[u8; 32]>,
+    root_cursor: u32,
+}
+
+/// Hashes two child nodes into their parent using the host's SHA3-256
+/// primitive (`hash_sha3_256`). This is standard SHA3-256, *not*
+/// Keccak-256 — the two use different padding and are not interchangeable
+/// — so an external ZK verifier circuit recomputing this tree's root must
+/// match SHA3-256 specifically. `concordium-std`'s crypto primitives don't
+/// expose a separate Keccak-256 (or a ZK-friendly arithmetic hash) to pick
+/// between, so there is no runtime hash choice here yet.
+fn hash_pair(crypto_primitives: &CryptoPrimitives, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut buf = [0u8; 64];
+    buf[..32].copy_from_slice(left);
+    buf[32..].copy_from_slice(right);
+    crypto_primitives.hash_sha3_256(&buf).0
+}
+
+/// Precomputes the zero-hash at every level (the root of an all-zero
+/// subtree), used to pad the right-hand side of the tree until it fills up.
+fn compute_zero_hashes(crypto_primitives: &CryptoPrimitives) -> Vec<[u8; 32]> {
+    let mut zero_hashes = Vec::with_capacity(TREE_DEPTH + 1);
+    zero_hashes.push([0u8; 32]);
+    for level in 0..TREE_DEPTH {
+        let prev = zero_hashes[level];
+        zero_hashes.push(hash_pair(crypto_primitives, &prev, &prev));
+    }
+    zero_hashes
+}
+
+/// Inserts a new leaf at `next_leaf_index`, recomputes the path to the root,
+/// and records the new root in the bounded root history. Never reuses a leaf
+/// index and rejects inserts once the tree is full.
+fn merkle_insert(
+    state: &mut State,
+    crypto_primitives: &CryptoPrimitives,
+    leaf: [u8; 32],
+) -> ContractResult<(u64, [u8; 32])> {
+    ensure!(state.next_leaf_index < (1u64 << TREE_DEPTH), Error::TreeFull);
+
+    let leaf_index = state.next_leaf_index;
+    let mut index = leaf_index;
+    let mut current = leaf;
+
+    for level in 0..TREE_DEPTH {
+        if index % 2 == 0 {
+            state.filled_subtrees[level] = current;
+            current = hash_pair(crypto_primitives, &current, &state.zero_hashes[level]);
+        } else {
+            current = hash_pair(crypto_primitives, &state.filled_subtrees[level], &current);
+        }
+        index /= 2;
+    }
+
+    state.next_leaf_index += 1;
+    state.current_root = current;
+
+    if state.root_history.len() < ROOT_HISTORY_SIZE {
+        state.root_history.push(current);
+    } else {
+        let cursor = (state.root_cursor as usize) % ROOT_HISTORY_SIZE;
+        let evicted = state.root_history[cursor];
+        state.known_roots.remove(&evicted);
+        state.root_history[cursor] = current;
+    }
+    state.root_cursor = (state.root_cursor + 1) % (ROOT_HISTORY_SIZE as u32);
+    state.known_roots.insert(current);
+
+    Ok((leaf_index, current))
 }
 
 impl State {
-    fn new(sb: &mut StateBuilder, admin: AccountAddress, idps: Vec<AccountAddress>) -> Self {
+    fn new(
+        sb: &mut StateBuilder,
+        admin: AccountAddress,
+        idps: Vec<AccountAddress>,
+        registry_metadata: RegistryMetadata,
+        crypto_primitives: &CryptoPrimitives,
+    ) -> Self {
         let mut idp_set = sb.new_set();
         for a in idps {
             idp_set.insert(a);
         }
+
+        let zero_hashes = compute_zero_hashes(crypto_primitives);
+        let filled_subtrees = zero_hashes[..TREE_DEPTH].to_vec();
+        let current_root = zero_hashes[TREE_DEPTH];
+
+        let mut known_roots = sb.new_set();
+        known_roots.insert(current_root);
+
         Self {
             admin,
             idps: idp_set,
+            idp_keys: sb.new_set(),
             verified: sb.new_map(),
             used_nullifiers: sb.new_set(),
-            revoked_at: sb.new_map(),
+            nonces: sb.new_map(),
+            revocation_nonces: sb.new_map(),
+            registry_metadata,
+            next_leaf_index: 0,
+            filled_subtrees,
+            zero_hashes,
+            current_root,
+            known_roots,
+            root_history: vec![current_root],
+            root_cursor: 1,
         }
     }
 }
@@ -91,12 +328,22 @@ impl State {
 pub struct InitParams {
     pub admin: AccountAddress,
     pub idps: Vec<AccountAddress>,
+    pub registry_metadata: RegistryMetadata,
 }
 
-#[init(contract = "zk_kyc_registry", parameter = "InitParams", error = "Error")]
-pub fn init(ctx: &InitContext, sb: &mut StateBuilder) -> ContractResult<State> {
+#[init(
+    contract = "zk_kyc_registry",
+    parameter = "InitParams",
+    error = "Error",
+    crypto_primitives
+)]
+pub fn init(
+    ctx: &InitContext,
+    sb: &mut StateBuilder,
+    crypto_primitives: &CryptoPrimitives,
+) -> ContractResult<State> {
     let params: InitParams = ctx.parameter_cursor().get().map_err(|_| Error::Parse)?;
-    Ok(State::new(sb, params.admin, params.idps))
+    Ok(State::new(sb, params.admin, params.idps, params.registry_metadata, crypto_primitives))
 }
 
 fn ensure_admin(ctx: &ReceiveContext, state: &State) -> ContractResult<()> {
@@ -113,6 +360,23 @@ fn ensure_idp(ctx: &ReceiveContext, state: &State) -> ContractResult<AccountAddr
     Ok(sender)
 }
 
+fn ensure_idp_key(state: &State, key: &PublicKeyEd25519) -> ContractResult<()> {
+    ensure!(state.idp_keys.contains(key), Error::NotIdpKey);
+    Ok(())
+}
+
+/// Rejects registering over an active credential, but allows re-registering
+/// a subject whose prior credential was revoked — matching the baseline
+/// behavior where `revoke` freed up the subject for `register` again.
+fn ensure_registerable(state: &State, subject: &AccountAddress) -> ContractResult<()> {
+    let already_active = state
+        .verified
+        .get(subject)
+        .map_or(false, |r| r.revoked_at.is_none());
+    ensure!(!already_active, Error::AlreadyRegistered);
+    Ok(())
+}
+
 // ============================================================================
 // ADMIN OPERATIONS
 // ============================================================================
@@ -281,6 +545,70 @@ pub fn remove_idps_batch(
     Ok(RemoveIdpsBatchResult { removed_count })
 }
 
+#[derive(Serial, Deserial, SchemaType)]
+pub struct AddIdpKeyParam {
+    pub key: PublicKeyEd25519,
+}
+
+#[receive(
+    contract = "zk_kyc_registry",
+    name = "add_idp_key",
+    parameter = "AddIdpKeyParam",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+pub fn add_idp_key(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger,
+) -> ContractResult<()> {
+    ensure_admin(ctx, &host.state)?;
+    let AddIdpKeyParam { key } = ctx.parameter_cursor().get().map_err(|_| Error::Parse)?;
+
+    host.state.idp_keys.insert(key);
+
+    logger.log(&Event::IdpKeyAdded { key }).ok();
+
+    Ok(())
+}
+
+#[derive(Serial, Deserial, SchemaType)]
+pub struct RemoveIdpKeyParam {
+    pub key: PublicKeyEd25519,
+}
+
+#[derive(Serial, SchemaType)]
+pub struct RemoveIdpKeyResult {
+    pub removed: bool,
+}
+
+#[receive(
+    contract = "zk_kyc_registry",
+    name = "remove_idp_key",
+    parameter = "RemoveIdpKeyParam",
+    error = "Error",
+    mutable,
+    enable_logger,
+    return_value = "RemoveIdpKeyResult"
+)]
+pub fn remove_idp_key(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger,
+) -> ContractResult<RemoveIdpKeyResult> {
+    ensure_admin(ctx, &host.state)?;
+    let RemoveIdpKeyParam { key } = ctx.parameter_cursor().get().map_err(|_| Error::Parse)?;
+
+    let removed = host.state.idp_keys.remove(&key);
+
+    if removed {
+        logger.log(&Event::IdpKeyRemoved { key }).ok();
+    }
+
+    Ok(RemoveIdpKeyResult { removed })
+}
+
 // ============================================================================
 // IDP OPERATIONS
 // ============================================================================
@@ -289,6 +617,9 @@ pub fn remove_idps_batch(
 pub struct RegisterParam {
     pub subject: AccountAddress,
     pub commitment: Commitment,
+    pub valid_from: Timestamp,
+    pub valid_until: Option<Timestamp>,
+    pub holder_key: Option<PublicKeyEd25519>,
 }
 
 #[receive(
@@ -297,170 +628,611 @@ pub struct RegisterParam {
     parameter = "RegisterParam",
     error = "Error",
     mutable,
-    enable_logger
+    enable_logger,
+    crypto_primitives
 )]
 pub fn register(
     ctx: &ReceiveContext,
     host: &mut Host<State>,
     logger: &mut Logger,
+    crypto_primitives: &CryptoPrimitives,
 ) -> ContractResult<()> {
     let idp = ensure_idp(ctx, &host.state)?;
     let RegisterParam {
         subject,
         commitment,
+        valid_from,
+        valid_until,
+        holder_key,
     } = ctx.parameter_cursor().get().map_err(|_| Error::Parse)?;
 
-    ensure!(host.state.verified.get(&subject).is_none(), Error::AlreadyRegistered);
+    ensure_registerable(&host.state, &subject)?;
+
+    host.state.verified.insert(subject, CredentialRecord {
+        commitment,
+        valid_from,
+        valid_until,
+        revoked_at: None,
+        holder_key,
+    });
+    let (leaf_index, _root) = merkle_insert(&mut host.state, crypto_primitives, commitment.0)?;
 
-    host.state.verified.insert(subject, commitment);
-    
-    // Clear revocation timestamp if re-registering
-    host.state.revoked_at.remove(&subject);
-    
     logger.log(&Event::Registered {
-        idp,
+        idp: Some(idp),
+        signer_key: None,
         subject,
         commitment,
+        leaf_index,
         timestamp: ctx.metadata().slot_time(),
     }).ok();
-    
+    logger.log(&Cis4Event::RegisterCredentialEvent {
+        subject,
+        commitment,
+        valid_from,
+        valid_until,
+    }).ok();
+
     Ok(())
 }
 
 #[derive(Serial, Deserial, SchemaType)]
-pub struct RevokeParam {
+pub struct RegisterSignedParam {
     pub subject: AccountAddress,
+    pub commitment: Commitment,
+    pub valid_from: Timestamp,
+    pub valid_until: Option<Timestamp>,
+    pub holder_key: Option<PublicKeyEd25519>,
+    pub nonce: u64,
+    pub signer_key: PublicKeyEd25519,
+    pub signature: SignatureEd25519,
 }
 
+/// Registers a subject from an off-chain-signed IdP attestation, so the
+/// IdP never has to be `ctx.sender()` or hold CCD to submit the
+/// transaction itself; anyone can relay this call on the IdP's behalf.
 #[receive(
     contract = "zk_kyc_registry",
-    name = "revoke",
-    parameter = "RevokeParam",
+    name = "register_signed",
+    parameter = "RegisterSignedParam",
     error = "Error",
     mutable,
-    enable_logger
+    enable_logger,
+    crypto_primitives
 )]
-pub fn revoke(
+pub fn register_signed(
     ctx: &ReceiveContext,
     host: &mut Host<State>,
     logger: &mut Logger,
+    crypto_primitives: &CryptoPrimitives,
 ) -> ContractResult<()> {
-    let idp = ensure_idp(ctx, &host.state)?;
-    let RevokeParam { subject } = ctx.parameter_cursor().get().map_err(|_| Error::Parse)?;
+    let RegisterSignedParam {
+        subject,
+        commitment,
+        valid_from,
+        valid_until,
+        holder_key,
+        nonce,
+        signer_key,
+        signature,
+    } = ctx.parameter_cursor().get().map_err(|_| Error::Parse)?;
 
-    ensure!(host.state.verified.get(&subject).is_some(), Error::NotRegistered);
+    ensure_idp_key(&host.state, &signer_key)?;
 
-    host.state.verified.remove(&subject);
-    
-    let timestamp = ctx.metadata().slot_time();
-    host.state.revoked_at.insert(subject, timestamp);
-    
-    logger.log(&Event::Revoked {
-        idp,
+    let expected_nonce = host.state.nonces.get(&subject).map_or(0, |n| *n);
+    ensure!(nonce == expected_nonce, Error::InvalidNonce);
+
+    let message = to_bytes(&(subject, commitment, valid_from, valid_until, holder_key, nonce));
+    ensure!(
+        crypto_primitives
+            .verify_ed25519_signature(signer_key, signature, &message),
+        Error::InvalidSignature
+    );
+
+    ensure_registerable(&host.state, &subject)?;
+
+    host.state.verified.insert(subject, CredentialRecord {
+        commitment,
+        valid_from,
+        valid_until,
+        revoked_at: None,
+        holder_key,
+    });
+    host.state.nonces.insert(subject, nonce + 1);
+    let (leaf_index, _root) = merkle_insert(&mut host.state, crypto_primitives, commitment.0)?;
+
+    logger.log(&Event::Registered {
+        idp: None,
+        signer_key: Some(signer_key),
         subject,
-        timestamp,
+        commitment,
+        leaf_index,
+        timestamp: ctx.metadata().slot_time(),
     }).ok();
-    
+    logger.log(&Cis4Event::RegisterCredentialEvent {
+        subject,
+        commitment,
+        valid_from,
+        valid_until,
+    }).ok();
+
     Ok(())
 }
 
 #[derive(Serial, Deserial, SchemaType)]
-pub struct UseNullifierParam {
-    pub nullifier: Nullifier,
-    pub domain: u16, // Domain separation: 1 = KYC, 2 = Age, 3 = Residency, etc.
+pub struct RenewParam {
+    pub subject: AccountAddress,
+    pub valid_until: Option<Timestamp>,
 }
 
+/// Lets any registered IdP extend (or shorten) the validity window of an
+/// already registered subject, without going through revoke + re-register.
+/// The record does not store which IdP registered the subject.
 #[receive(
     contract = "zk_kyc_registry",
-    name = "use_nullifier",
-    parameter = "UseNullifierParam",
+    name = "renew",
+    parameter = "RenewParam",
     error = "Error",
     mutable,
     enable_logger
 )]
-pub fn use_nullifier(
+pub fn renew(
     ctx: &ReceiveContext,
     host: &mut Host<State>,
     logger: &mut Logger,
 ) -> ContractResult<()> {
-    let UseNullifierParam { nullifier, domain } =
+    let idp = ensure_idp(ctx, &host.state)?;
+    let RenewParam { subject, valid_until } =
         ctx.parameter_cursor().get().map_err(|_| Error::Parse)?;
 
-    let key = NullifierKey { domain, nullifier };
-    
-    ensure!(
-        !host.state.used_nullifiers.contains(&key),
-        Error::NullifierUsed
-    );
-
-    host.state.used_nullifiers.insert(key);
-
-    // Link nullifier to sender if they are verified
-    let maybe_subject = match ctx.sender() {
-        Address::Account(a) if host.state.verified.get(&a).is_some() => Some(a),
-        _ => None,
-    };
+    {
+        let mut record = host.state.verified.get_mut(&subject).ok_or(Error::NotRegistered)?;
+        record.valid_until = valid_until;
+    }
 
-    logger.log(&Event::NullifierUsed {
-        by: maybe_subject,
-        nullifier,
-        domain,
+    logger.log(&Event::Renewed {
+        idp,
+        subject,
+        valid_until,
         timestamp: ctx.metadata().slot_time(),
     }).ok();
 
     Ok(())
 }
 
-// ============================================================================
-// VIEW FUNCTIONS
-// ============================================================================
-
-#[receive(
-    contract = "zk_kyc_registry",
-    name = "is_verified",
-    parameter = "AccountAddress",
-    return_value = "bool"
-)]
-pub fn is_verified(ctx: &ReceiveContext, host: &Host<State>) -> ReceiveResult<bool> {
-    let addr: AccountAddress = ctx.parameter_cursor().get()?;
-    Ok(host.state.verified.get(&addr).is_some())
+#[derive(Serial, Deserial, SchemaType)]
+pub struct RevokeParam {
+    pub subject: AccountAddress,
 }
 
 #[receive(
     contract = "zk_kyc_registry",
-    name = "get_commitment",
-    parameter = "AccountAddress",
-    return_value = "Option<Commitment>"
+    name = "revoke",
+    parameter = "RevokeParam",
+    error = "Error",
+    mutable,
+    enable_logger
 )]
-pub fn get_commitment(ctx: &ReceiveContext, host: &Host<State>) -> ReceiveResult<Option<Commitment>> {
-    let addr: AccountAddress = ctx.parameter_cursor().get()?;
-    Ok(host.state.verified.get(&addr).map(|c| *c))
-}
+pub fn revoke(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger,
+) -> ContractResult<()> {
+    let idp = ensure_idp(ctx, &host.state)?;
+    let RevokeParam { subject } = ctx.parameter_cursor().get().map_err(|_| Error::Parse)?;
 
-#[receive(
-    contract = "zk_kyc_registry",
-    name = "is_idp",
-    parameter = "AccountAddress",
-    return_value = "bool"
-)]
-pub fn is_idp(ctx: &ReceiveContext, host: &Host<State>) -> ReceiveResult<bool> {
-    let addr: AccountAddress = ctx.parameter_cursor().get()?;
-    Ok(host.state.idps.contains(&addr))
-}
+    let timestamp = ctx.metadata().slot_time();
+    {
+        let mut record = host.state.verified.get_mut(&subject).ok_or(Error::NotRegistered)?;
+        record.revoked_at = Some(timestamp);
+    }
 
-#[receive(
-    contract = "zk_kyc_registry",
-    name = "get_admin",
-    return_value = "AccountAddress"
-)]
-pub fn get_admin(_ctx: &ReceiveContext, host: &Host<State>) -> ReceiveResult<AccountAddress> {
-    Ok(host.state.admin)
+    logger.log(&Event::Revoked {
+        idp: Some(idp),
+        subject,
+        timestamp,
+    }).ok();
+    logger.log(&Cis4Event::RevokeCredentialEvent { subject, timestamp }).ok();
+
+    Ok(())
 }
 
 #[derive(Serial, Deserial, SchemaType)]
-pub struct NullifierUsedParam {
-    pub nullifier: Nullifier,
-    pub domain: u16,
+pub struct RegisterBatchParam {
+    pub registrations: Vec<RegisterParam>,
+}
+
+#[derive(Serial, SchemaType)]
+pub struct RegisterBatchResult {
+    pub registered: u32,
+    pub skipped: u32,
+    pub tree_full: u32,
+}
+
+/// Registers many subjects in one transaction, e.g. during an onboarding
+/// drive, instead of one `register` call per subject.
+#[receive(
+    contract = "zk_kyc_registry",
+    name = "register_batch",
+    parameter = "RegisterBatchParam",
+    error = "Error",
+    mutable,
+    enable_logger,
+    return_value = "RegisterBatchResult",
+    crypto_primitives
+)]
+pub fn register_batch(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger,
+    crypto_primitives: &CryptoPrimitives,
+) -> ContractResult<RegisterBatchResult> {
+    let idp = ensure_idp(ctx, &host.state)?;
+    let RegisterBatchParam { registrations } =
+        ctx.parameter_cursor().get().map_err(|_| Error::Parse)?;
+
+    let timestamp = ctx.metadata().slot_time();
+    let mut registered = 0u32;
+    let mut skipped = 0u32;
+    let mut tree_full = 0u32;
+
+    for RegisterParam { subject, commitment, valid_from, valid_until, holder_key } in registrations {
+        if ensure_registerable(&host.state, &subject).is_err() {
+            skipped += 1;
+            continue;
+        }
+
+        // Insert into the commitment tree first: a full tree must only skip
+        // this entry, not abort the whole batch (Concordium reverts all
+        // state on a propagated Reject), so don't commit to `verified`
+        // until the leaf insert has actually succeeded.
+        let (leaf_index, _root) = match merkle_insert(&mut host.state, crypto_primitives, commitment.0) {
+            Ok(result) => result,
+            Err(Error::TreeFull) => {
+                tree_full += 1;
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        host.state.verified.insert(subject, CredentialRecord {
+            commitment,
+            valid_from,
+            valid_until,
+            revoked_at: None,
+            holder_key,
+        });
+        registered += 1;
+
+        logger.log(&Event::Registered {
+            idp: Some(idp),
+            signer_key: None,
+            subject,
+            commitment,
+            leaf_index,
+            timestamp,
+        }).ok();
+        logger.log(&Cis4Event::RegisterCredentialEvent {
+            subject,
+            commitment,
+            valid_from,
+            valid_until,
+        }).ok();
+    }
+
+    Ok(RegisterBatchResult { registered, skipped, tree_full })
+}
+
+#[derive(Serial, Deserial, SchemaType)]
+pub struct RevokeBatchParam {
+    pub revocations: Vec<RevokeParam>,
+}
+
+#[derive(Serial, SchemaType)]
+pub struct RevokeBatchResult {
+    pub revoked: u32,
+    pub not_found: u32,
+}
+
+/// Revokes many subjects in one transaction; mirrors `register_batch`.
+#[receive(
+    contract = "zk_kyc_registry",
+    name = "revoke_batch",
+    parameter = "RevokeBatchParam",
+    error = "Error",
+    mutable,
+    enable_logger,
+    return_value = "RevokeBatchResult"
+)]
+pub fn revoke_batch(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger,
+) -> ContractResult<RevokeBatchResult> {
+    let idp = ensure_idp(ctx, &host.state)?;
+    let RevokeBatchParam { revocations } =
+        ctx.parameter_cursor().get().map_err(|_| Error::Parse)?;
+
+    let timestamp = ctx.metadata().slot_time();
+    let mut revoked = 0u32;
+    let mut not_found = 0u32;
+
+    for RevokeParam { subject } in revocations {
+        let was_found = {
+            match host.state.verified.get_mut(&subject) {
+                Some(mut record) => {
+                    record.revoked_at = Some(timestamp);
+                    true
+                }
+                None => false,
+            }
+        };
+
+        if was_found {
+            revoked += 1;
+            logger.log(&Event::Revoked { idp: Some(idp), subject, timestamp }).ok();
+            logger.log(&Cis4Event::RevokeCredentialEvent { subject, timestamp }).ok();
+        } else {
+            not_found += 1;
+        }
+    }
+
+    Ok(RevokeBatchResult { revoked, not_found })
+}
+
+#[derive(Serial, Deserial, SchemaType)]
+pub struct RevokeByHolderParam {
+    pub subject: AccountAddress,
+    pub reason: RevokeReason,
+    pub holder_key: PublicKeyEd25519,
+    pub signature: SignatureEd25519,
+}
+
+/// Lets the credential holder revoke their own credential with a signed,
+/// nonce-protected message, so a subject can kill a compromised credential
+/// without depending on the issuing IdP being online.
+#[receive(
+    contract = "zk_kyc_registry",
+    name = "revoke_by_holder",
+    parameter = "RevokeByHolderParam",
+    error = "Error",
+    mutable,
+    enable_logger,
+    crypto_primitives
+)]
+pub fn revoke_by_holder(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger,
+    crypto_primitives: &CryptoPrimitives,
+) -> ContractResult<()> {
+    let RevokeByHolderParam { subject, reason, holder_key, signature } =
+        ctx.parameter_cursor().get().map_err(|_| Error::Parse)?;
+
+    let stored_holder_key = host
+        .state
+        .verified
+        .get(&subject)
+        .ok_or(Error::NotRegistered)?
+        .holder_key
+        .ok_or(Error::Unauthorized)?;
+    ensure!(stored_holder_key == holder_key, Error::Unauthorized);
+
+    let nonce = host.state.revocation_nonces.get(&subject).map_or(0, |n| *n);
+    let message = to_bytes(&(subject, nonce, reason));
+    ensure!(
+        crypto_primitives
+            .verify_ed25519_signature(holder_key, signature, &message),
+        Error::InvalidSignature
+    );
+    host.state.revocation_nonces.insert(subject, nonce + 1);
+
+    let timestamp = ctx.metadata().slot_time();
+    {
+        let mut record = host.state.verified.get_mut(&subject).ok_or(Error::NotRegistered)?;
+        record.revoked_at = Some(timestamp);
+    }
+
+    logger.log(&Event::Revoked { idp: None, subject, timestamp }).ok();
+    logger.log(&Cis4Event::RevokeCredentialEvent { subject, timestamp }).ok();
+
+    Ok(())
+}
+
+#[derive(Serial, Deserial, SchemaType)]
+pub struct UseNullifierParam {
+    pub nullifier: Nullifier,
+    pub domain: u16, // Domain separation: 1 = KYC, 2 = Age, 3 = Residency, etc.
+    /// The commitment-tree root the caller's membership proof was built
+    /// against; must still be in the known-roots window.
+    pub root: [u8; 32],
+}
+
+#[receive(
+    contract = "zk_kyc_registry",
+    name = "use_nullifier",
+    parameter = "UseNullifierParam",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+pub fn use_nullifier(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger,
+) -> ContractResult<()> {
+    let UseNullifierParam { nullifier, domain, root } =
+        ctx.parameter_cursor().get().map_err(|_| Error::Parse)?;
+
+    ensure!(host.state.known_roots.contains(&root), Error::UnknownRoot);
+
+    let key = NullifierKey { domain, nullifier };
+
+    ensure!(
+        !host.state.used_nullifiers.contains(&key),
+        Error::NullifierUsed
+    );
+
+    host.state.used_nullifiers.insert(key);
+
+    // Link nullifier to sender if they are verified
+    let now = ctx.metadata().slot_time();
+    let maybe_subject = match ctx.sender() {
+        Address::Account(a)
+            if host
+                .state
+                .verified
+                .get(&a)
+                .map_or(false, |r| r.status(now) == CredentialStatus::Active) =>
+        {
+            Some(a)
+        }
+        _ => None,
+    };
+
+    logger.log(&Event::NullifierUsed {
+        by: maybe_subject,
+        nullifier,
+        domain,
+        timestamp: ctx.metadata().slot_time(),
+    }).ok();
+
+    Ok(())
+}
+
+// ============================================================================
+// VIEW FUNCTIONS
+// ============================================================================
+
+#[receive(
+    contract = "zk_kyc_registry",
+    name = "is_verified",
+    parameter = "AccountAddress",
+    return_value = "bool"
+)]
+pub fn is_verified(ctx: &ReceiveContext, host: &Host<State>) -> ReceiveResult<bool> {
+    let addr: AccountAddress = ctx.parameter_cursor().get()?;
+    let now = ctx.metadata().slot_time();
+    Ok(host
+        .state
+        .verified
+        .get(&addr)
+        .map_or(false, |r| r.status(now) == CredentialStatus::Active))
+}
+
+#[receive(
+    contract = "zk_kyc_registry",
+    name = "credential_status",
+    parameter = "AccountAddress",
+    return_value = "CredentialStatus"
+)]
+pub fn credential_status(ctx: &ReceiveContext, host: &Host<State>) -> ReceiveResult<CredentialStatus> {
+    let addr: AccountAddress = ctx.parameter_cursor().get()?;
+    let now = ctx.metadata().slot_time();
+    Ok(host
+        .state
+        .verified
+        .get(&addr)
+        .map_or(CredentialStatus::NotActivated, |r| r.status(now)))
+}
+
+/// CIS-0 `supports` query: lets wallets/indexers discover that this
+/// registry implements CIS-4 before attempting to use it as one.
+#[receive(
+    contract = "zk_kyc_registry",
+    name = "supports",
+    parameter = "SupportsQueryParams",
+    return_value = "SupportsQueryResponse"
+)]
+pub fn supports(ctx: &ReceiveContext, _host: &Host<State>) -> ReceiveResult<SupportsQueryResponse> {
+    let SupportsQueryParams { queries } = ctx.parameter_cursor().get()?;
+    let results = queries
+        .into_iter()
+        .map(|id| {
+            if id.0 == "CIS-4" {
+                SupportResult::Support
+            } else {
+                SupportResult::NoSupport
+            }
+        })
+        .collect();
+    Ok(SupportsQueryResponse { results })
+}
+
+/// CIS-4 `registryMetadata` view: issuer metadata and credential schema
+/// URLs so indexers can render and validate credentials from this registry.
+#[receive(
+    contract = "zk_kyc_registry",
+    name = "registryMetadata",
+    return_value = "RegistryMetadata"
+)]
+pub fn registry_metadata(_ctx: &ReceiveContext, host: &Host<State>) -> ReceiveResult<RegistryMetadata> {
+    Ok(host.state.registry_metadata.clone())
+}
+
+/// CIS-4 `issuer` view: the account administering this credential registry.
+#[receive(
+    contract = "zk_kyc_registry",
+    name = "issuer",
+    return_value = "AccountAddress"
+)]
+pub fn issuer(_ctx: &ReceiveContext, host: &Host<State>) -> ReceiveResult<AccountAddress> {
+    Ok(host.state.admin)
+}
+
+/// CIS-4 `credentialStatus` entrypoint, keyed by credential identifier
+/// rather than our own `AccountAddress` parameter naming, for standard
+/// indexers that expect the CIS-4 shape.
+#[receive(
+    contract = "zk_kyc_registry",
+    name = "credentialStatus",
+    parameter = "CredentialId",
+    return_value = "CredentialStatus"
+)]
+pub fn credential_status_cis4(ctx: &ReceiveContext, host: &Host<State>) -> ReceiveResult<CredentialStatus> {
+    let CredentialId(addr) = ctx.parameter_cursor().get()?;
+    let now = ctx.metadata().slot_time();
+    Ok(host
+        .state
+        .verified
+        .get(&addr)
+        .map_or(CredentialStatus::NotActivated, |r| r.status(now)))
+}
+
+#[receive(
+    contract = "zk_kyc_registry",
+    name = "get_commitment",
+    parameter = "AccountAddress",
+    return_value = "Option<Commitment>"
+)]
+pub fn get_commitment(ctx: &ReceiveContext, host: &Host<State>) -> ReceiveResult<Option<Commitment>> {
+    let addr: AccountAddress = ctx.parameter_cursor().get()?;
+    Ok(host.state.verified.get(&addr).map(|r| r.commitment))
+}
+
+#[receive(
+    contract = "zk_kyc_registry",
+    name = "is_idp",
+    parameter = "AccountAddress",
+    return_value = "bool"
+)]
+pub fn is_idp(ctx: &ReceiveContext, host: &Host<State>) -> ReceiveResult<bool> {
+    let addr: AccountAddress = ctx.parameter_cursor().get()?;
+    Ok(host.state.idps.contains(&addr))
+}
+
+#[receive(
+    contract = "zk_kyc_registry",
+    name = "get_admin",
+    return_value = "AccountAddress"
+)]
+pub fn get_admin(_ctx: &ReceiveContext, host: &Host<State>) -> ReceiveResult<AccountAddress> {
+    Ok(host.state.admin)
+}
+
+#[derive(Serial, Deserial, SchemaType)]
+pub struct NullifierUsedParam {
+    pub nullifier: Nullifier,
+    pub domain: u16,
 }
 
 #[receive(
@@ -483,7 +1255,27 @@ pub fn nullifier_used(ctx: &ReceiveContext, host: &Host<State>) -> ReceiveResult
 )]
 pub fn get_revoked_at(ctx: &ReceiveContext, host: &Host<State>) -> ReceiveResult<Option<Timestamp>> {
     let addr: AccountAddress = ctx.parameter_cursor().get()?;
-    Ok(host.state.revoked_at.get(&addr).map(|t| *t))
+    Ok(host.state.verified.get(&addr).and_then(|r| r.revoked_at))
+}
+
+#[receive(
+    contract = "zk_kyc_registry",
+    name = "current_root",
+    return_value = "[u8; 32]"
+)]
+pub fn current_root(_ctx: &ReceiveContext, host: &Host<State>) -> ReceiveResult<[u8; 32]> {
+    Ok(host.state.current_root)
+}
+
+#[receive(
+    contract = "zk_kyc_registry",
+    name = "is_known_root",
+    parameter = "[u8; 32]",
+    return_value = "bool"
+)]
+pub fn is_known_root(ctx: &ReceiveContext, host: &Host<State>) -> ReceiveResult<bool> {
+    let root: [u8; 32] = ctx.parameter_cursor().get()?;
+    Ok(host.state.known_roots.contains(&root))
 }
 
 // ============================================================================
@@ -494,27 +1286,269 @@ pub fn get_revoked_at(ctx: &ReceiveContext, host: &Host<State>) -> ReceiveResult
 mod tests {
     use super::*;
     use concordium_std::test_infrastructure::*;
+    // Real keypair signing (dev-dependency: ed25519_dalek) so the
+    // register_signed/revoke_by_holder tests exercise
+    // verify_ed25519_signature's actual message encoding, not just the
+    // pre-checks that reject an unregistered or mismatched key before
+    // signature verification ever runs.
+    use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+
+    fn test_keypair(seed: u8) -> Keypair {
+        let secret = SecretKey::from_bytes(&[seed; 32]).expect("valid secret key seed");
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    fn test_registry_metadata() -> RegistryMetadata {
+        RegistryMetadata {
+            issuer_metadata: MetadataUrl { url: "https://example.com/issuer".into(), hash: None },
+            credential_schema_url: MetadataUrl { url: "https://example.com/schema".into(), hash: None },
+        }
+    }
 
     #[concordium_test]
     fn test_init() {
         let mut ctx = TestInitContext::empty();
         let admin = AccountAddress([0u8; 32]);
         let idp1 = AccountAddress([1u8; 32]);
-        
+
         let params = InitParams {
             admin,
             idps: vec![idp1],
+            registry_metadata: test_registry_metadata(),
         };
-        
+
         let param_bytes = to_bytes(&params);
         ctx.set_parameter(&param_bytes);
-        
+
         let mut sb = TestStateBuilder::new();
-        let result = init(&ctx, &mut sb);
-        
+        let crypto_primitives = TestCryptoPrimitives::new();
+        let result = init(&ctx, &mut sb, &crypto_primitives);
+
         assert!(result.is_ok());
     }
 
+    #[concordium_test]
+    fn test_register_signed_rejects_unknown_key() {
+        let admin = AccountAddress([0u8; 32]);
+        let subject = AccountAddress([2u8; 32]);
+
+        let mut sb = TestStateBuilder::new();
+        let crypto_primitives = TestCryptoPrimitives::new();
+        let state = State::new(&mut sb, admin, vec![], test_registry_metadata(), &crypto_primitives);
+        let mut host = TestHost::new(state, sb);
+
+        let params = RegisterSignedParam {
+            subject,
+            commitment: Commitment([7u8; 32]),
+            valid_from: Timestamp::from_timestamp_millis(0),
+            valid_until: None,
+            holder_key: None,
+            nonce: 0,
+            signer_key: PublicKeyEd25519([1u8; 32]),
+            signature: SignatureEd25519([0u8; 64]),
+        };
+        let param_bytes = to_bytes(&params);
+
+        let mut ctx = TestReceiveContext::empty();
+        ctx.set_parameter(&param_bytes);
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(0));
+
+        let mut logger = TestLogger::init();
+        let result = register_signed(&ctx, &mut host, &mut logger, &crypto_primitives);
+
+        assert_eq!(result, Err(Error::NotIdpKey));
+    }
+
+    #[concordium_test]
+    fn test_register_signed_accepts_valid_attestation() {
+        let admin = AccountAddress([0u8; 32]);
+        let subject = AccountAddress([2u8; 32]);
+
+        let mut sb = TestStateBuilder::new();
+        let crypto_primitives = TestCryptoPrimitives::new();
+        let state = State::new(&mut sb, admin, vec![], test_registry_metadata(), &crypto_primitives);
+        let mut host = TestHost::new(state, sb);
+
+        let keypair = test_keypair(7);
+        let signer_key = PublicKeyEd25519(keypair.public.to_bytes());
+        host.state.idp_keys.insert(signer_key);
+
+        let commitment = Commitment([7u8; 32]);
+        let valid_from = Timestamp::from_timestamp_millis(0);
+        let valid_until = None;
+        let holder_key = None;
+        let nonce = 0u64;
+
+        let message = to_bytes(&(subject, commitment, valid_from, valid_until, holder_key, nonce));
+        let signature = SignatureEd25519(keypair.sign(&message).to_bytes());
+
+        let params = RegisterSignedParam {
+            subject,
+            commitment,
+            valid_from,
+            valid_until,
+            holder_key,
+            nonce,
+            signer_key,
+            signature,
+        };
+        let param_bytes = to_bytes(&params);
+
+        let mut ctx = TestReceiveContext::empty();
+        ctx.set_parameter(&param_bytes);
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(0));
+
+        let mut logger = TestLogger::init();
+        let result = register_signed(&ctx, &mut host, &mut logger, &crypto_primitives);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            host.state.verified.get(&subject).map(|r| r.commitment),
+            Some(commitment)
+        );
+        assert_eq!(host.state.nonces.get(&subject).map(|n| *n), Some(1));
+    }
+
+    #[concordium_test]
+    fn test_register_signed_rejects_replayed_nonce() {
+        let admin = AccountAddress([0u8; 32]);
+        let subject = AccountAddress([2u8; 32]);
+
+        let mut sb = TestStateBuilder::new();
+        let crypto_primitives = TestCryptoPrimitives::new();
+        let state = State::new(&mut sb, admin, vec![], test_registry_metadata(), &crypto_primitives);
+        let mut host = TestHost::new(state, sb);
+
+        let keypair = test_keypair(7);
+        let signer_key = PublicKeyEd25519(keypair.public.to_bytes());
+        host.state.idp_keys.insert(signer_key);
+
+        let commitment = Commitment([7u8; 32]);
+        let valid_from = Timestamp::from_timestamp_millis(0);
+        let valid_until = None;
+        let holder_key = None;
+        let nonce = 0u64;
+
+        let message = to_bytes(&(subject, commitment, valid_from, valid_until, holder_key, nonce));
+        let signature = SignatureEd25519(keypair.sign(&message).to_bytes());
+
+        let params = RegisterSignedParam {
+            subject,
+            commitment,
+            valid_from,
+            valid_until,
+            holder_key,
+            nonce,
+            signer_key,
+            signature,
+        };
+        let param_bytes = to_bytes(&params);
+
+        let mut ctx = TestReceiveContext::empty();
+        ctx.set_parameter(&param_bytes);
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(0));
+
+        let mut logger = TestLogger::init();
+        register_signed(&ctx, &mut host, &mut logger, &crypto_primitives).expect("first call succeeds");
+
+        // Relaying the exact same signed attestation again must be rejected:
+        // the nonce has already advanced past what the signature covers.
+        let result = register_signed(&ctx, &mut host, &mut logger, &crypto_primitives);
+
+        assert_eq!(result, Err(Error::InvalidNonce));
+    }
+
+    #[concordium_test]
+    fn test_register_rejects_active_duplicate() {
+        let admin = AccountAddress([0u8; 32]);
+        let idp = AccountAddress([1u8; 32]);
+        let subject = AccountAddress([2u8; 32]);
+
+        let mut sb = TestStateBuilder::new();
+        let crypto_primitives = TestCryptoPrimitives::new();
+        let state = State::new(&mut sb, admin, vec![idp], test_registry_metadata(), &crypto_primitives);
+        let mut host = TestHost::new(state, sb);
+
+        let params = RegisterParam {
+            subject,
+            commitment: Commitment([5u8; 32]),
+            valid_from: Timestamp::from_timestamp_millis(0),
+            valid_until: None,
+            holder_key: None,
+        };
+        let param_bytes = to_bytes(&params);
+
+        let mut ctx = TestReceiveContext::empty();
+        ctx.set_parameter(&param_bytes);
+        ctx.set_sender(Address::Account(idp));
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(0));
+
+        let mut logger = TestLogger::init();
+        register(&ctx, &mut host, &mut logger, &crypto_primitives).expect("first register");
+        let result = register(&ctx, &mut host, &mut logger, &crypto_primitives);
+
+        assert_eq!(result, Err(Error::AlreadyRegistered));
+    }
+
+    #[concordium_test]
+    fn test_register_allowed_after_revoke() {
+        let admin = AccountAddress([0u8; 32]);
+        let idp = AccountAddress([1u8; 32]);
+        let subject = AccountAddress([2u8; 32]);
+
+        let mut sb = TestStateBuilder::new();
+        let crypto_primitives = TestCryptoPrimitives::new();
+        let state = State::new(&mut sb, admin, vec![idp], test_registry_metadata(), &crypto_primitives);
+        let mut host = TestHost::new(state, sb);
+
+        let register_params = RegisterParam {
+            subject,
+            commitment: Commitment([5u8; 32]),
+            valid_from: Timestamp::from_timestamp_millis(0),
+            valid_until: None,
+            holder_key: None,
+        };
+        let register_bytes = to_bytes(&register_params);
+
+        let mut ctx = TestReceiveContext::empty();
+        ctx.set_parameter(&register_bytes);
+        ctx.set_sender(Address::Account(idp));
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(0));
+
+        let mut logger = TestLogger::init();
+        register(&ctx, &mut host, &mut logger, &crypto_primitives).expect("register");
+
+        let revoke_params = RevokeParam { subject };
+        let revoke_bytes = to_bytes(&revoke_params);
+        let mut revoke_ctx = TestReceiveContext::empty();
+        revoke_ctx.set_parameter(&revoke_bytes);
+        revoke_ctx.set_sender(Address::Account(idp));
+        revoke_ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(1));
+        revoke(&revoke_ctx, &mut host, &mut logger).expect("revoke");
+
+        // Re-registering the same subject with new terms must succeed and
+        // reset the revoked credential rather than returning AlreadyRegistered.
+        let new_params = RegisterParam {
+            subject,
+            commitment: Commitment([6u8; 32]),
+            valid_from: Timestamp::from_timestamp_millis(2),
+            valid_until: None,
+            holder_key: None,
+        };
+        let new_bytes = to_bytes(&new_params);
+        let mut re_ctx = TestReceiveContext::empty();
+        re_ctx.set_parameter(&new_bytes);
+        re_ctx.set_sender(Address::Account(idp));
+        re_ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(2));
+
+        register(&re_ctx, &mut host, &mut logger, &crypto_primitives).expect("re-register after revoke");
+
+        let record = host.state.verified.get(&subject).expect("record exists");
+        assert_eq!(record.revoked_at, None);
+        assert_eq!(record.commitment, Commitment([6u8; 32]));
+    }
+
     #[concordium_test]
     fn test_domain_separation() {
         // Test that same nullifier can be used in different domains
@@ -524,4 +1558,313 @@ mod tests {
         
         assert_ne!(key1, key2);
     }
+
+    #[concordium_test]
+    fn test_credential_record_status() {
+        let now = Timestamp::from_timestamp_millis(100);
+        let record = CredentialRecord {
+            commitment: Commitment([0u8; 32]),
+            valid_from: Timestamp::from_timestamp_millis(50),
+            valid_until: Some(Timestamp::from_timestamp_millis(150)),
+            revoked_at: None,
+            holder_key: None,
+        };
+
+        assert_eq!(
+            record.status(Timestamp::from_timestamp_millis(10)),
+            CredentialStatus::NotActivated
+        );
+        assert_eq!(record.status(now), CredentialStatus::Active);
+        assert_eq!(
+            record.status(Timestamp::from_timestamp_millis(200)),
+            CredentialStatus::Expired
+        );
+
+        let revoked = CredentialRecord {
+            revoked_at: Some(now),
+            ..record
+        };
+        assert_eq!(revoked.status(now), CredentialStatus::Revoked);
+    }
+
+    #[concordium_test]
+    fn test_register_batch_skips_already_registered() {
+        let admin = AccountAddress([0u8; 32]);
+        let idp = AccountAddress([1u8; 32]);
+        let subject_a = AccountAddress([2u8; 32]);
+        let subject_b = AccountAddress([3u8; 32]);
+
+        let mut sb = TestStateBuilder::new();
+        let crypto_primitives = TestCryptoPrimitives::new();
+        let state = State::new(&mut sb, admin, vec![idp], test_registry_metadata(), &crypto_primitives);
+        let mut host = TestHost::new(state, sb);
+
+        let make_param = |subject| RegisterParam {
+            subject,
+            commitment: Commitment([9u8; 32]),
+            valid_from: Timestamp::from_timestamp_millis(0),
+            valid_until: None,
+            holder_key: None,
+        };
+
+        // Pre-register subject_a directly so the batch call has to skip it.
+        host.state.verified.insert(subject_a, CredentialRecord {
+            commitment: Commitment([1u8; 32]),
+            valid_from: Timestamp::from_timestamp_millis(0),
+            valid_until: None,
+            revoked_at: None,
+            holder_key: None,
+        });
+
+        let params = RegisterBatchParam {
+            registrations: vec![make_param(subject_a), make_param(subject_b)],
+        };
+        let param_bytes = to_bytes(&params);
+
+        let mut ctx = TestReceiveContext::empty();
+        ctx.set_parameter(&param_bytes);
+        ctx.set_sender(Address::Account(idp));
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(0));
+
+        let mut logger = TestLogger::init();
+        let result = register_batch(&ctx, &mut host, &mut logger, &crypto_primitives).expect("register_batch");
+
+        assert_eq!(result.registered, 1);
+        assert_eq!(result.skipped, 1);
+    }
+
+    #[concordium_test]
+    fn test_register_batch_continues_past_tree_full_entry() {
+        let admin = AccountAddress([0u8; 32]);
+        let idp = AccountAddress([1u8; 32]);
+        let subject_a = AccountAddress([2u8; 32]);
+        let subject_b = AccountAddress([3u8; 32]);
+
+        let mut sb = TestStateBuilder::new();
+        let crypto_primitives = TestCryptoPrimitives::new();
+        let mut state = State::new(&mut sb, admin, vec![idp], test_registry_metadata(), &crypto_primitives);
+        // Fill the tree so every entry's insert hits TreeFull: the batch must
+        // count each one under tree_full and carry on past the first rather
+        // than rejecting (and so reverting) the whole call.
+        state.next_leaf_index = 1u64 << TREE_DEPTH;
+        let mut host = TestHost::new(state, sb);
+
+        let make_param = |subject| RegisterParam {
+            subject,
+            commitment: Commitment([9u8; 32]),
+            valid_from: Timestamp::from_timestamp_millis(0),
+            valid_until: None,
+            holder_key: None,
+        };
+
+        let params = RegisterBatchParam {
+            registrations: vec![make_param(subject_a), make_param(subject_b)],
+        };
+        let param_bytes = to_bytes(&params);
+
+        let mut ctx = TestReceiveContext::empty();
+        ctx.set_parameter(&param_bytes);
+        ctx.set_sender(Address::Account(idp));
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(0));
+
+        let mut logger = TestLogger::init();
+        let result = register_batch(&ctx, &mut host, &mut logger, &crypto_primitives).expect("register_batch");
+
+        assert_eq!(result.registered, 0);
+        assert_eq!(result.tree_full, 2);
+        assert!(host.state.verified.get(&subject_a).is_none());
+        assert!(host.state.verified.get(&subject_b).is_none());
+    }
+
+    #[concordium_test]
+    fn test_supports_cis4() {
+        let admin = AccountAddress([0u8; 32]);
+
+        let mut sb = TestStateBuilder::new();
+        let crypto_primitives = TestCryptoPrimitives::new();
+        let state = State::new(&mut sb, admin, vec![], test_registry_metadata(), &crypto_primitives);
+        let host = TestHost::new(state, sb);
+
+        let params = SupportsQueryParams {
+            queries: vec![
+                StandardIdentifierOwned("CIS-4".into()),
+                StandardIdentifierOwned("CIS-2".into()),
+            ],
+        };
+        let param_bytes = to_bytes(&params);
+
+        let mut ctx = TestReceiveContext::empty();
+        ctx.set_parameter(&param_bytes);
+
+        let response = supports(&ctx, &host).expect("supports");
+
+        assert_eq!(response.results, vec![SupportResult::Support, SupportResult::NoSupport]);
+    }
+
+    #[concordium_test]
+    fn test_revoke_by_holder_rejects_unregistered_subject() {
+        let admin = AccountAddress([0u8; 32]);
+        let subject = AccountAddress([2u8; 32]);
+
+        let mut sb = TestStateBuilder::new();
+        let crypto_primitives = TestCryptoPrimitives::new();
+        let state = State::new(&mut sb, admin, vec![], test_registry_metadata(), &crypto_primitives);
+        let mut host = TestHost::new(state, sb);
+
+        let params = RevokeByHolderParam {
+            subject,
+            reason: RevokeReason::HolderRequested,
+            holder_key: PublicKeyEd25519([1u8; 32]),
+            signature: SignatureEd25519([0u8; 64]),
+        };
+        let param_bytes = to_bytes(&params);
+
+        let mut ctx = TestReceiveContext::empty();
+        ctx.set_parameter(&param_bytes);
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(0));
+
+        let mut logger = TestLogger::init();
+        let result = revoke_by_holder(&ctx, &mut host, &mut logger, &crypto_primitives);
+
+        assert_eq!(result, Err(Error::NotRegistered));
+    }
+
+    #[concordium_test]
+    fn test_revoke_by_holder_accepts_valid_signature() {
+        let admin = AccountAddress([0u8; 32]);
+        let subject = AccountAddress([2u8; 32]);
+
+        let mut sb = TestStateBuilder::new();
+        let crypto_primitives = TestCryptoPrimitives::new();
+        let state = State::new(&mut sb, admin, vec![], test_registry_metadata(), &crypto_primitives);
+        let mut host = TestHost::new(state, sb);
+
+        let keypair = test_keypair(11);
+        let holder_key = PublicKeyEd25519(keypair.public.to_bytes());
+
+        host.state.verified.insert(subject, CredentialRecord {
+            commitment: Commitment([3u8; 32]),
+            valid_from: Timestamp::from_timestamp_millis(0),
+            valid_until: None,
+            revoked_at: None,
+            holder_key: Some(holder_key),
+        });
+
+        let reason = RevokeReason::HolderRequested;
+        let nonce = 0u64;
+        let message = to_bytes(&(subject, nonce, reason));
+        let signature = SignatureEd25519(keypair.sign(&message).to_bytes());
+
+        let params = RevokeByHolderParam { subject, reason, holder_key, signature };
+        let param_bytes = to_bytes(&params);
+
+        let mut ctx = TestReceiveContext::empty();
+        ctx.set_parameter(&param_bytes);
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(5));
+
+        let mut logger = TestLogger::init();
+        let result = revoke_by_holder(&ctx, &mut host, &mut logger, &crypto_primitives);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            host.state.verified.get(&subject).map(|r| r.revoked_at),
+            Some(Some(Timestamp::from_timestamp_millis(5)))
+        );
+        assert_eq!(host.state.revocation_nonces.get(&subject).map(|n| *n), Some(1));
+
+        // Replaying the exact same signed message must fail: the nonce it
+        // was signed over has already been consumed.
+        let result = revoke_by_holder(&ctx, &mut host, &mut logger, &crypto_primitives);
+        assert_eq!(result, Err(Error::InvalidSignature));
+    }
+
+    /// Recomputes a root from scratch (pairwise hash, zero-hash padding on
+    /// the right), as an independent oracle for `merkle_insert`'s
+    /// incremental bookkeeping.
+    fn naive_root(crypto_primitives: &CryptoPrimitives, leaves: &[[u8; 32]]) -> [u8; 32] {
+        let zero_hashes = compute_zero_hashes(crypto_primitives);
+        let mut level = leaves.to_vec();
+        for zero in zero_hashes.iter().take(TREE_DEPTH) {
+            let mut next = Vec::with_capacity((level.len() + 1) / 2);
+            let mut i = 0;
+            while i < level.len() {
+                let left = level[i];
+                let right = if i + 1 < level.len() { level[i + 1] } else { *zero };
+                next.push(hash_pair(crypto_primitives, &left, &right));
+                i += 2;
+            }
+            level = next;
+        }
+        level[0]
+    }
+
+    #[concordium_test]
+    fn test_merkle_insert_matches_naive_root() {
+        let crypto_primitives = TestCryptoPrimitives::new();
+        let admin = AccountAddress([0u8; 32]);
+        let mut sb = TestStateBuilder::new();
+        let mut state = State::new(&mut sb, admin, vec![], test_registry_metadata(), &crypto_primitives);
+
+        let leaves = [[1u8; 32], [2u8; 32], [3u8; 32]];
+        let mut last_leaf_index = None;
+        for leaf in leaves {
+            let (leaf_index, _root) = merkle_insert(&mut state, &crypto_primitives, leaf).expect("insert");
+            last_leaf_index = Some(leaf_index);
+        }
+
+        assert_eq!(last_leaf_index, Some(2));
+        assert_eq!(state.current_root, naive_root(&crypto_primitives, &leaves));
+    }
+
+    #[concordium_test]
+    fn test_merkle_insert_rejects_past_tree_capacity() {
+        let crypto_primitives = TestCryptoPrimitives::new();
+        let admin = AccountAddress([0u8; 32]);
+        let mut sb = TestStateBuilder::new();
+        let mut state = State::new(&mut sb, admin, vec![], test_registry_metadata(), &crypto_primitives);
+
+        state.next_leaf_index = (1u64 << TREE_DEPTH) - 1;
+        let (leaf_index, _root) =
+            merkle_insert(&mut state, &crypto_primitives, [4u8; 32]).expect("last slot");
+        assert_eq!(leaf_index, (1u64 << TREE_DEPTH) - 1);
+
+        let result = merkle_insert(&mut state, &crypto_primitives, [5u8; 32]);
+        assert_eq!(result, Err(Error::TreeFull));
+    }
+
+    #[concordium_test]
+    fn test_root_history_evicts_after_window_and_use_nullifier_rejects_stale_root() {
+        let crypto_primitives = TestCryptoPrimitives::new();
+        let admin = AccountAddress([0u8; 32]);
+        let mut sb = TestStateBuilder::new();
+        let state = State::new(&mut sb, admin, vec![], test_registry_metadata(), &crypto_primitives);
+        let mut host = TestHost::new(state, sb);
+
+        let (_, first_root) =
+            merkle_insert(&mut host.state, &crypto_primitives, [1u8; 32]).expect("insert");
+        assert!(host.state.known_roots.contains(&first_root));
+
+        for i in 0..ROOT_HISTORY_SIZE {
+            let leaf = [(i as u8).wrapping_add(2); 32];
+            merkle_insert(&mut host.state, &crypto_primitives, leaf).expect("insert");
+        }
+
+        assert!(!host.state.known_roots.contains(&first_root));
+
+        let params = UseNullifierParam {
+            nullifier: Nullifier([9u8; 32]),
+            domain: 1,
+            root: first_root,
+        };
+        let param_bytes = to_bytes(&params);
+
+        let mut ctx = TestReceiveContext::empty();
+        ctx.set_parameter(&param_bytes);
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(0));
+
+        let mut logger = TestLogger::init();
+        let result = use_nullifier(&ctx, &mut host, &mut logger);
+
+        assert_eq!(result, Err(Error::UnknownRoot));
+    }
 }
\ No newline at end of file